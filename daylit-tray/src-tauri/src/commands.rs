@@ -1,8 +1,49 @@
-use crate::state::{AppState, Settings, WebhookPayload};
-use std::fs;
-use tauri::{AppHandle, Emitter, Manager, State, WebviewWindow};
+use crate::state::{
+    AppState, NotificationHistoryEntry, QueuedNotification, Settings, current_unix_time,
+};
+use tauri::{AppHandle, Emitter, EventTarget, Manager, State, WebviewWindow};
 use tauri_plugin_autostart::ManagerExt;
 
+// The windows that render settings today. `settings-updated` is only
+// relevant to them, so we target each directly instead of broadcasting to
+// every webview (including `notification_dialog`, which never reads it).
+const SETTINGS_EVENT_TARGETS: [&str; 2] = ["main", "settings"];
+
+fn emit_settings_updated(app: &AppHandle, settings: &Settings) -> Result<(), String> {
+    for label in SETTINGS_EVENT_TARGETS {
+        app.emit_to(label, "settings-updated", settings)
+            .map_err(|e| e.to_string())?;
+    }
+
+    // Fan out to any additional windows (e.g. a future second notification
+    // dialog) that have explicitly subscribed, without waking every webview.
+    let state: State<AppState> = app.state();
+    let extra_subscribers = state.event_subscribers.subscribers("settings-updated");
+    if !extra_subscribers.is_empty() {
+        app.emit_filter(
+            "settings-updated",
+            settings,
+            |target| matches!(target, EventTarget::WebviewWindow { label } if extra_subscribers.contains(label)),
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// Registers `window_label` as a listener for `event_class` beyond the
+/// fixed [`SETTINGS_EVENT_TARGETS`], e.g. a second notification dialog that
+/// also wants to know when settings change.
+#[tauri::command]
+pub fn subscribe_to_event(event_class: String, window_label: String, state: State<AppState>) {
+    state.event_subscribers.subscribe(&event_class, &window_label);
+}
+
+#[tauri::command]
+pub fn unsubscribe_from_event(event_class: String, window_label: String, state: State<AppState>) {
+    state.event_subscribers.unsubscribe(&event_class, &window_label);
+}
+
 #[tauri::command]
 pub fn get_settings(state: State<AppState>) -> Result<Settings, String> {
     Ok(Settings::load(&state.settings))
@@ -20,29 +61,16 @@ pub async fn save_settings(settings: Settings, app: AppHandle) -> Result<(), Str
         autostart_manager.disable().map_err(|e| e.to_string())?;
     }
 
-    // Handle lockfile location change
-    {
-        let mut lockfile_guard = state.lockfile_path.lock().unwrap();
-        let new_config_dir = if let Some(dir) = &settings.lockfile_dir {
-            std::path::PathBuf::from(dir)
-        } else {
-            app.path().app_config_dir().unwrap()
-        };
-        let new_path = new_config_dir.join("daylit-tray.lock");
-
-        if let Some(old_path) = lockfile_guard.as_ref() {
-            if *old_path != new_path {
-                if old_path.exists() {
-                    let content = fs::read_to_string(old_path).map_err(|e| e.to_string())?;
-                    fs::remove_file(old_path).map_err(|e| e.to_string())?;
-
-                    fs::create_dir_all(&new_config_dir).map_err(|e| e.to_string())?;
-                    fs::write(&new_path, content).map_err(|e| e.to_string())?;
-                }
-                *lockfile_guard = Some(new_path);
-            }
-        }
-    }
+    // Anything that changes where/how the webhook server binds (lockfile
+    // location, bind address, TLS config) only takes effect on a fresh
+    // bind, so restart it through the managed lifecycle rather than
+    // patching the running server's lockfile in place.
+    let previous_settings = Settings::load(&state.settings);
+    let needs_restart = previous_settings.lockfile_dir != settings.lockfile_dir
+        || previous_settings.bind_address != settings.bind_address
+        || previous_settings.enable_tls != settings.enable_tls
+        || previous_settings.tls_cert_path != settings.tls_cert_path
+        || previous_settings.tls_key_path != settings.tls_key_path;
 
     // Save to store
     state.settings.set(
@@ -51,13 +79,35 @@ pub async fn save_settings(settings: Settings, app: AppHandle) -> Result<(), Str
     );
     state.settings.save().map_err(|e| e.to_string())?;
 
-    app.emit("settings-updated", &settings)
-        .map_err(|e| e.to_string())
+    if needs_restart {
+        state.webhook_server.restart(app.clone());
+    }
+
+    emit_settings_updated(&app, &settings)
+}
+
+/// Called by the dialog when its current notification's `duration_ms` has
+/// elapsed; pops the next queued notification (recording it in history as
+/// delivered now) or `None` if the queue is empty, in which case the frontend
+/// closes the dialog.
+#[tauri::command]
+pub fn get_next_notification(state: State<AppState>) -> Option<QueuedNotification> {
+    state.queue.advance(current_unix_time())
+}
+
+#[tauri::command]
+pub fn peek_queue(state: State<AppState>) -> Vec<QueuedNotification> {
+    state.queue.peek()
+}
+
+#[tauri::command]
+pub fn get_notification_history(state: State<AppState>) -> Vec<NotificationHistoryEntry> {
+    state.queue.history()
 }
 
 #[tauri::command]
-pub fn get_notification_payload(state: State<AppState>) -> Option<WebhookPayload> {
-    state.payload.lock().unwrap().clone()
+pub fn clear_history(state: State<AppState>) {
+    state.queue.clear_history();
 }
 
 #[tauri::command]
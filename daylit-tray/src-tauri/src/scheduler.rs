@@ -17,7 +17,7 @@ pub trait CommandRunner {
     fn run(&self, program: &str, args: &[&str]) -> std::io::Result<CommandOutput>;
 }
 
-struct RealCommandRunner;
+pub(crate) struct RealCommandRunner;
 
 impl CommandRunner for RealCommandRunner {
     fn run(&self, program: &str, args: &[&str]) -> std::io::Result<CommandOutput> {
@@ -1,51 +1,285 @@
+use crate::scheduler::{CommandRunner, RealCommandRunner};
 use crate::state::LOCKFILE_NAME;
-use crate::state::{AppState, Settings, UpdatePayload, WebhookPayload};
+use crate::state::{
+    AppState, QueuedNotification, Settings, UpdatePayload, WebhookPayload, current_unix_time,
+    parse_webhook_payload,
+};
+use hmac::{Hmac, Mac};
 use rand::Rng;
 use rand::distributions::Alphanumeric;
 use rand::rngs::OsRng;
+use serde::Serialize;
+use sha2::Sha256;
+use std::collections::HashSet;
 use std::fs;
+use std::io::Cursor;
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
+use std::sync::{Arc, Mutex, mpsc};
 use std::thread;
 use subtle::ConstantTimeEq;
 use tauri::{AppHandle, Emitter, Manager, State};
 use tauri_plugin_log::log::{error, info};
 use tauri_plugin_notification::NotificationExt;
-use tiny_http::{Header, Response, Server};
+use tiny_http::{Header, Method, Request, Response, Server};
 
-fn validate_request(headers: &[Header], expected_secret: &str) -> bool {
+// Requests are dispatched to this many worker threads so a slow
+// notification render can't stall the auth/health check for the next
+// request in line.
+const WORKER_POOL_SIZE: usize = 4;
+
+type HmacSha256 = Hmac<Sha256>;
+
+// Requests must be signed within this many seconds of "now" in either
+// direction; anything older (or further in the future) is rejected even if
+// the signature is otherwise valid.
+const REQUEST_WINDOW_SECS: i64 = 30;
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// `HMAC-SHA256(key = shared secret, msg = timestamp + "." + raw_body)`,
+/// hex-encoded. Computed identically on both ends so the secret itself
+/// never has to travel on the wire.
+fn compute_signature(secret: &str, timestamp: &str, body: &str) -> String {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts a key of any size");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    hex_encode(&mac.finalize().into_bytes())
+}
+
+/// Every response carries an explicit `Content-Type`, `Cache-Control:
+/// no-store` (nothing here is ever safe to cache), and a small set of
+/// restrictive security headers, following the hardening practice in
+/// vaultwarden's `util.rs`.
+fn hardened_response(status_code: u16, content_type: &str, body: String) -> Response<Cursor<Vec<u8>>> {
+    let mut response = Response::from_string(body).with_status_code(status_code);
+    for (name, value) in [
+        ("Content-Type", content_type),
+        ("Cache-Control", "no-store"),
+        ("X-Content-Type-Options", "nosniff"),
+        ("X-Frame-Options", "DENY"),
+        ("Referrer-Policy", "no-referrer"),
+    ] {
+        if let Ok(header) = Header::from_bytes(name, value) {
+            response.add_header(header);
+        }
+    }
+    response
+}
+
+fn text_response(status_code: u16, body: impl Into<String>) -> Response<Cursor<Vec<u8>>> {
+    hardened_response(status_code, "text/plain; charset=utf-8", body.into())
+}
+
+fn json_response<T: Serialize>(status_code: u16, body: &T) -> Response<Cursor<Vec<u8>>> {
+    let json = serde_json::to_string(body).unwrap_or_else(|_| "{}".to_string());
+    hardened_response(status_code, "application/json", json)
+}
+
+/// Send a response and log (but don't propagate) any write failure, since
+/// there's nothing more we can do once the handler has decided how to
+/// answer a request.
+fn finish(request: Request, response: Response<Cursor<Vec<u8>>>) {
+    if let Err(e) = request.respond(response) {
+        error!("Failed to respond to request: {}", e);
+    }
+}
+
+#[derive(Serialize)]
+struct HealthStatus<'a> {
+    status: &'a str,
+    port: u16,
+    address: Option<String>,
+    pid: u32,
+    version: &'a str,
+}
+
+#[derive(Serialize)]
+struct QueueStatus {
+    pending: usize,
+    last_delivered_at: Option<i64>,
+}
+
+fn header_value<'a>(headers: &'a [Header], name: &str) -> Option<&'a str> {
     headers
         .iter()
-        .find(|h| {
-            h.field
-                .as_str()
-                .as_str()
-                .eq_ignore_ascii_case("X-Daylit-Secret")
-        })
-        .map(|h| {
-            // Use constant-time comparison to prevent timing-based side-channel attacks
-            h.value
-                .as_str()
-                .as_bytes()
-                .ct_eq(expected_secret.as_bytes())
-                .into()
-        })
-        .unwrap_or(false)
+        .find(|h| h.field.as_str().as_str().eq_ignore_ascii_case(name))
+        .map(|h| h.value.as_str())
 }
 
-pub fn start_webhook_server(app_handle: AppHandle) {
-    thread::spawn(move || {
-        // Bind to port 0 to let the OS choose an available port
-        let server = match Server::http("127.0.0.1:0") {
-            Ok(s) => s,
-            Err(e) => {
-                error!("Failed to create webhook server: {}", e);
+/// `Host` must name this server by its loopback address/port, and no
+/// `Origin` header may be present at all: a plain `tiny_http` server never
+/// receives same-origin requests, so any `Origin` means the request came
+/// from a browser context, which makes it a candidate for a DNS-rebinding
+/// attack regardless of what `Host` claims.
+fn host_and_origin_valid(headers: &[Header], port: u16) -> bool {
+    let host_valid = header_value(headers, "Host")
+        .and_then(|host| host.rsplit_once(':'))
+        .map(|(host, request_port)| is_loopback_host(host) && request_port == port.to_string())
+        .unwrap_or(false);
+    if !host_valid {
+        return false;
+    }
+
+    header_value(headers, "Origin").is_none()
+}
+
+/// Validate an inbound webhook request against the `X-Daylit-Timestamp` /
+/// `X-Daylit-Signature` scheme: the `Host`/`Origin` check above must pass,
+/// the signature must match what we'd compute over the exact body we read,
+/// the timestamp must fall inside the replay window, and the
+/// `(timestamp, signature)` pair must not have been seen before (which
+/// would mean the request is a captured replay).
+fn validate_request(
+    headers: &[Header],
+    body: &str,
+    expected_secret: &str,
+    port: u16,
+    seen_signatures: &Mutex<HashSet<(i64, String)>>,
+) -> bool {
+    if !host_and_origin_valid(headers, port) {
+        return false;
+    }
+
+    let Some(timestamp_str) = header_value(headers, "X-Daylit-Timestamp") else {
+        return false;
+    };
+    let Some(signature) = header_value(headers, "X-Daylit-Signature") else {
+        return false;
+    };
+
+    let Ok(timestamp) = timestamp_str.parse::<i64>() else {
+        return false;
+    };
+
+    let now = current_unix_time();
+    if (now - timestamp).abs() > REQUEST_WINDOW_SECS {
+        return false;
+    }
+
+    let expected_signature = compute_signature(expected_secret, timestamp_str, body);
+    // Use constant-time comparison to prevent timing-based side-channel attacks
+    let signature_valid: bool = expected_signature
+        .as_bytes()
+        .ct_eq(signature.as_bytes())
+        .into();
+    if !signature_valid {
+        return false;
+    }
+
+    let mut seen = seen_signatures.lock().expect("Failed to acquire seen_signatures lock");
+    seen.retain(|(ts, _)| (now - ts).abs() <= REQUEST_WINDOW_SECS);
+
+    let key = (timestamp, signature.to_string());
+    if seen.contains(&key) {
+        return false;
+    }
+    seen.insert(key);
+    true
+}
+
+/// A running server's accept loop and worker pool, kept around so
+/// [`WebhookServer::shutdown`] can unblock and join them.
+struct RunningServer {
+    server: Arc<Server>,
+    accept_thread: thread::JoinHandle<()>,
+    workers: Vec<thread::JoinHandle<()>>,
+}
+
+/// Owns the webhook `tiny_http::Server`'s lifecycle: binding, a small
+/// worker pool so a slow notification render can't stall the auth/health
+/// check for the next request, and a clean shutdown that tears down the
+/// lockfile and secret. Intended to be started once at app launch and torn
+/// down from the `RunEvent` exit hook in `lib.rs`.
+#[derive(Default)]
+pub struct WebhookServer {
+    running: Mutex<Option<RunningServer>>,
+}
+
+impl WebhookServer {
+    /// Bind a fresh server, write the lockfile/secret, and spawn the accept
+    /// loop plus worker pool. No-op if already running.
+    pub fn start(&self, app_handle: AppHandle) {
+        let mut running = self
+            .running
+            .lock()
+            .expect("Failed to acquire webhook server lock");
+        if running.is_some() {
+            info!("Webhook server already running; ignoring start()");
+            return;
+        }
+
+        let state: State<AppState> = app_handle.state();
+        let settings = Settings::load(&state.settings);
+
+        // Defaults to loopback-only, OS-assigned port. A non-loopback
+        // `bind_address` exposes the webhook to other hosts on the network,
+        // which is only safe to do over TLS - a plaintext LAN listener would
+        // let anyone on the network read every request body and its HMAC
+        // signature.
+        let bind_address = settings
+            .bind_address
+            .clone()
+            .unwrap_or_else(|| "127.0.0.1:0".to_string());
+        let host = bind_address.rsplit_once(':').map_or(bind_address.as_str(), |(host, _)| host);
+        if !is_loopback_host(host) && !settings.enable_tls {
+            error!(
+                "Refusing to bind webhook server to non-loopback address '{}' without enable_tls",
+                bind_address
+            );
+            return;
+        }
+
+        let server = if settings.enable_tls {
+            let (Some(cert_path), Some(key_path)) =
+                (settings.tls_cert_path.as_ref(), settings.tls_key_path.as_ref())
+            else {
+                error!("enable_tls is set but tls_cert_path/tls_key_path are missing");
                 return;
+            };
+            let certificate = match fs::read(cert_path) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    error!("Failed to read tls_cert_path '{}': {}", cert_path, e);
+                    return;
+                }
+            };
+            let private_key = match fs::read(key_path) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    error!("Failed to read tls_key_path '{}': {}", key_path, e);
+                    return;
+                }
+            };
+            match Server::https(
+                &bind_address,
+                tiny_http::SslConfig {
+                    certificate,
+                    private_key,
+                },
+            ) {
+                Ok(s) => s,
+                Err(e) => {
+                    error!("Failed to create TLS webhook server: {}", e);
+                    return;
+                }
+            }
+        } else {
+            match Server::http(&bind_address) {
+                Ok(s) => s,
+                Err(e) => {
+                    error!("Failed to create webhook server: {}", e);
+                    return;
+                }
             }
         };
 
-        let port = match server.server_addr().to_ip() {
-            Some(addr) => addr.port(),
+        let bound_addr = match server.server_addr().to_ip() {
+            Some(addr) => addr,
             None => {
                 error!(
                     "Failed to get webhook server IP address - webhook server will not be available for notifications"
@@ -53,10 +287,7 @@ pub fn start_webhook_server(app_handle: AppHandle) {
                 return;
             }
         };
-
-        // --- Create Lock File ---
-        let state: State<AppState> = app_handle.state();
-        let settings = Settings::load(&state.settings);
+        let port = bound_addr.port();
 
         // Generate a cryptographically secure random secret (32 characters)
         // Using OsRng which is explicitly a cryptographically secure RNG
@@ -92,8 +323,17 @@ pub fn start_webhook_server(app_handle: AppHandle) {
             return;
         }
         let lock_file_path = config_dir.join(LOCKFILE_NAME);
+        // A previous run that was killed (rather than exiting through
+        // `shutdown()`) can leave a lockfile behind whose port no longer
+        // answers; clear it before we write our own so a companion CLI
+        // doesn't keep reading a dead port/secret.
+        remove_stale_lockfile(&lock_file_path);
+
         let pid = std::process::id();
-        let lock_content = format!("{}|{}|{}", port, pid, secret);
+        // Publish the full bound address (not just the port) so a companion
+        // CLI on another host knows which interface to call, once LAN
+        // binding is in use.
+        let lock_content = format!("{}|{}|{}", bound_addr, pid, secret);
         if let Err(e) = fs::write(&lock_file_path, lock_content) {
             error!("Failed to write lock file: {}", e);
             return;
@@ -115,100 +355,296 @@ pub fn start_webhook_server(app_handle: AppHandle) {
             .lock()
             .expect("Failed to acquire lockfile_path lock") = Some(lock_file_path);
 
-        info!("Webhook server started on port: {}", port);
+        // Store the bound port/address so the `/health` endpoint can report
+        // them.
+        *state.port.lock().expect("Failed to acquire port lock") = Some(port);
+        *state
+            .bound_address
+            .lock()
+            .expect("Failed to acquire bound_address lock") = Some(bound_addr.to_string());
+
+        info!("Webhook server started on: {}", bound_addr);
 
-        for mut request in server.incoming_requests() {
-            if request.method().as_str() != "POST" {
-                continue;
-            }
+        let server = Arc::new(server);
 
-            // Validate X-Daylit-Secret header
-            let auth_valid = {
-                let state: State<AppState> = app_handle.state();
-                let expected_secret = state.secret.lock().expect("Failed to acquire secret lock");
+        // The accept thread only reads requests off the socket and hands
+        // them to the worker pool over a channel; it never blocks on
+        // request handling, so `shutdown` can unblock it independently of
+        // whatever the workers are doing.
+        let (sender, receiver) = mpsc::channel::<Request>();
+        let receiver = Arc::new(Mutex::new(receiver));
 
-                if let Some(expected) = expected_secret.as_ref() {
-                    validate_request(request.headers(), expected)
-                } else {
-                    // If no secret is set (shouldn't happen), reject
-                    false
+        let workers = (0..WORKER_POOL_SIZE)
+            .map(|_| {
+                let receiver = Arc::clone(&receiver);
+                let worker_handle = app_handle.clone();
+                thread::spawn(move || {
+                    loop {
+                        let next = {
+                            let receiver = receiver
+                                .lock()
+                                .expect("Failed to acquire webhook worker lock");
+                            receiver.recv()
+                        };
+                        match next {
+                            Ok(request) => handle_request(&worker_handle, request),
+                            // Sender was dropped: the accept loop stopped, so
+                            // there's nothing left to wait for.
+                            Err(_) => break,
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        let accept_server = Arc::clone(&server);
+        let accept_thread = thread::spawn(move || {
+            for request in accept_server.incoming_requests() {
+                if sender.send(request).is_err() {
+                    break;
                 }
-            };
+            }
+        });
 
-            if !auth_valid {
-                error!("Unauthorized request: missing or invalid X-Daylit-Secret header");
-                let response = Response::from_string("Unauthorized").with_status_code(401);
-                if let Err(e) = request.respond(response) {
-                    error!("Failed to respond with error: {}", e);
+        *running = Some(RunningServer {
+            server,
+            accept_thread,
+            workers,
+        });
+    }
+
+    /// Rebind after a settings change (e.g. a new lockfile directory) by
+    /// shutting the current server down and starting a fresh one.
+    pub fn restart(&self, app_handle: AppHandle) {
+        self.shutdown(&app_handle);
+        self.start(app_handle);
+    }
+
+    /// Stop accepting new requests, join the worker pool, remove the
+    /// lockfile, and clear the secret so a stale one can't validate future
+    /// requests. No-op if not running.
+    pub fn shutdown(&self, app_handle: &AppHandle) {
+        let running = self
+            .running
+            .lock()
+            .expect("Failed to acquire webhook server lock")
+            .take();
+
+        if let Some(running) = running {
+            running.server.unblock();
+            if let Err(e) = running.accept_thread.join() {
+                error!("Webhook accept thread panicked: {:?}", e);
+            }
+            for worker in running.workers {
+                if let Err(e) = worker.join() {
+                    error!("Webhook worker thread panicked: {:?}", e);
                 }
-                continue;
             }
+        }
 
-            let mut content = String::new();
-            if let Err(e) = request.as_reader().read_to_string(&mut content) {
-                error!("Failed to read request body: {}", e);
-                continue;
+        let state: State<AppState> = app_handle.state();
+        if let Some(path) = state
+            .lockfile_path
+            .lock()
+            .expect("Failed to acquire lockfile_path lock")
+            .take()
+        {
+            if let Err(e) = fs::remove_file(&path) {
+                error!("Failed to remove lock file: {}", e);
             }
+        }
+        *state.secret.lock().expect("Failed to acquire secret lock") = None;
+        *state.port.lock().expect("Failed to acquire port lock") = None;
+        *state
+            .bound_address
+            .lock()
+            .expect("Failed to acquire bound_address lock") = None;
+    }
+}
+
+/// `bind_address` hosts that never leave the machine, and so are exempt
+/// from the `enable_tls` requirement.
+fn is_loopback_host(host: &str) -> bool {
+    host == "127.0.0.1" || host == "::1" || host.eq_ignore_ascii_case("localhost")
+}
 
-            if let Ok(payload) = serde_json::from_str::<WebhookPayload>(&content) {
-                let state: State<AppState> = app_handle.state();
-                *state
-                    .payload
-                    .lock()
-                    .expect("Failed to acquire payload lock") = Some(payload.clone());
+fn parse_lockfile_pid(content: &str) -> Option<u32> {
+    content.split('|').nth(1)?.parse().ok()
+}
+
+// Relies on the `sysinfo` crate, which already abstracts over per-platform
+// process enumeration so we don't have to shell out to `kill -0`/`tasklist`.
+fn is_pid_alive(pid: u32) -> bool {
+    let mut system = sysinfo::System::new();
+    system.refresh_processes(
+        sysinfo::ProcessesToUpdate::Some(&[sysinfo::Pid::from_u32(pid)]),
+        true,
+    );
+    system.process(sysinfo::Pid::from_u32(pid)).is_some()
+}
+
+/// Remove `lock_file_path` if it exists and records a PID that's no longer
+/// alive, e.g. a previous run was killed before it could clean up after
+/// itself. Safe to call unconditionally before binding.
+fn remove_stale_lockfile(lock_file_path: &std::path::Path) {
+    let Ok(content) = fs::read_to_string(lock_file_path) else {
+        return;
+    };
+    let Some(pid) = parse_lockfile_pid(&content) else {
+        return;
+    };
+    if !is_pid_alive(pid) {
+        info!("Removing stale lock file left by dead pid {}", pid);
+        let _ = fs::remove_file(lock_file_path);
+    }
+}
+
+/// Dispatch a single accepted request by method, then respond. Run on a
+/// worker thread so one slow render can't delay the next request.
+fn handle_request(app_handle: &AppHandle, request: Request) {
+    match request.method() {
+        Method::Get => handle_get_request(app_handle, request),
+        Method::Post => handle_post_request(app_handle, request),
+        other => {
+            error!("Rejected request with unsupported method: {:?}", other);
+            finish(request, text_response(405, "Method Not Allowed"));
+        }
+    }
+}
+
+/// Read-only diagnostics, unauthenticated since they expose nothing beyond
+/// what you'd already know from holding the lockfile (the port) or running
+/// the app (queue depth, last delivery time).
+fn handle_get_request(app_handle: &AppHandle, request: Request) {
+    let state: State<AppState> = app_handle.state();
+
+    match request.url() {
+        "/health" => {
+            let port = state.port.lock().expect("Failed to acquire port lock");
+            let address = state
+                .bound_address
+                .lock()
+                .expect("Failed to acquire bound_address lock");
+            let body = HealthStatus {
+                status: "ok",
+                port: port.unwrap_or(0),
+                address: address.clone(),
+                pid: std::process::id(),
+                version: env!("CARGO_PKG_VERSION"),
+            };
+            finish(request, json_response(200, &body));
+        }
+        "/status" => {
+            let body = QueueStatus {
+                pending: state.queue.peek().len(),
+                last_delivered_at: state.queue.last_delivered_at(),
+            };
+            finish(request, json_response(200, &body));
+        }
+        _ => finish(request, text_response(404, "Not Found")),
+    }
+}
 
+/// Validate, parse, and dispatch a webhook payload, then respond.
+fn handle_post_request(app_handle: &AppHandle, mut request: Request) {
+    // The signature covers the raw body, so we must read it before
+    // we can validate the request at all.
+    let mut content = String::new();
+    if let Err(e) = request.as_reader().read_to_string(&mut content) {
+        error!("Failed to read request body: {}", e);
+        return;
+    }
+
+    let auth_valid = {
+        let state: State<AppState> = app_handle.state();
+        let expected_secret = state.secret.lock().expect("Failed to acquire secret lock");
+        let port = *state.port.lock().expect("Failed to acquire port lock");
+
+        if let (Some(expected), Some(port)) = (expected_secret.as_ref(), port) {
+            validate_request(request.headers(), &content, expected, port, &state.seen_signatures)
+        } else {
+            // If no secret/port is set (shouldn't happen), reject
+            false
+        }
+    };
+
+    if !auth_valid {
+        error!(
+            "Unauthorized request: bad Host/Origin, or missing/invalid/replayed X-Daylit-Timestamp/X-Daylit-Signature"
+        );
+        finish(request, text_response(401, "Unauthorized"));
+        return;
+    }
+
+    if let Ok(payload) = parse_webhook_payload(&content) {
+        let state: State<AppState> = app_handle.state();
+
+        match payload {
+            WebhookPayload::Notify {
+                text,
+                duration_ms,
+                priority,
+            } => {
                 // Check if we should use native notifications
                 let settings = Settings::load(&state.settings);
-                
+
                 if settings.use_native_notifications {
                     // Use native system notifications
-                    // Note: The duration_ms field from the payload is not used here as
-                    // native notification duration is controlled by the operating system.
-                    // Custom notifications (else branch) do respect the duration_ms setting.
+                    // Note: duration_ms is not used here as native notification
+                    // duration is controlled by the operating system. Custom
+                    // notifications (else branch) do respect duration_ms.
                     info!("Using native notification");
                     if let Err(e) = app_handle
                         .notification()
                         .builder()
                         .title("Daylit")
-                        .body(&payload.text)
+                        .body(&text)
                         .show()
                     {
                         error!("Failed to show native notification: {}", e);
                     }
                 } else {
-                    // Use custom window notification (existing behavior)
-                    info!("Received webhook payload. Scheduling on main thread.");
+                    // Queue it rather than clobbering whatever the dialog is
+                    // currently showing; the frontend advances through the
+                    // queue itself as each notification's duration elapses.
+                    info!("Received webhook payload. Queueing for display.");
+                    state.queue.enqueue(QueuedNotification {
+                        text,
+                        duration_ms,
+                        priority,
+                    });
+
                     let app_handle_clone = app_handle.clone();
                     if let Err(e) = app_handle.run_on_main_thread(move || {
                         info!("Running on main thread.");
-                        // --- Re-use or Create Window Logic ---
+                        let state: State<AppState> = app_handle_clone.state();
+
                         if let Some(existing_window) =
                             app_handle_clone.get_webview_window("notification_dialog")
                         {
-                            info!("Dialog exists. Re-using and sending new data.");
+                            info!("Dialog exists. New notification stays queued.");
                             if let Err(e) = existing_window.set_focus() {
                                 error!("Failed to set window focus: {}", e);
                             }
-                            if let Err(e) = existing_window.emit(
-                                "update_notification",
-                                &UpdatePayload {
-                                    text: payload.text,
-                                    duration_ms: payload.duration_ms,
-                                },
-                            ) {
-                                error!("Failed to emit update notification: {}", e);
-                            }
                         } else {
                             info!("Dialog does not exist. Creating a new one.");
-                            if let Some(main_window) = app_handle_clone.get_webview_window("main") {
+                            let Some(next) = state.queue.advance(current_unix_time())
+                            else {
+                                return;
+                            };
+
+                            if let Some(main_window) =
+                                app_handle_clone.get_webview_window("main")
+                            {
                                 if let Ok(Some(monitor)) = main_window.primary_monitor() {
                                     let monitor_size = monitor.size();
                                     let dialog_width = 1000.0;
                                     let dialog_height = 100.0;
-                                    let pos_x = (monitor_size.width as f64 - dialog_width) / 2.0;
+                                    let pos_x =
+                                        (monitor_size.width as f64 - dialog_width) / 2.0;
                                     let pos_y = 60.0;
 
-                                    if let Err(e) = tauri::WebviewWindowBuilder::new(
+                                    match tauri::WebviewWindowBuilder::new(
                                         &app_handle_clone,
                                         "notification_dialog",
                                         tauri::WebviewUrl::App("/notification".into()),
@@ -220,7 +656,24 @@ pub fn start_webhook_server(app_handle: AppHandle) {
                                     .transparent(true)
                                     .build()
                                     {
-                                        error!("Failed to build notification dialog: {}", e);
+                                        Ok(window) => {
+                                            if let Err(e) = window.emit(
+                                                "update_notification",
+                                                &UpdatePayload {
+                                                    text: next.text,
+                                                    duration_ms: next.duration_ms,
+                                                },
+                                            ) {
+                                                error!(
+                                                    "Failed to emit update notification: {}",
+                                                    e
+                                                );
+                                            }
+                                        }
+                                        Err(e) => error!(
+                                            "Failed to build notification dialog: {}",
+                                            e
+                                        ),
                                     }
                                 } else {
                                     error!("Failed to get primary monitor");
@@ -233,20 +686,75 @@ pub fn start_webhook_server(app_handle: AppHandle) {
                         error!("Failed to run on main thread: {}", e);
                     }
                 }
-
-                let response = Response::from_string("Notification triggered");
-                if let Err(e) = request.respond(response) {
-                    error!("Failed to respond to webhook request: {}", e);
+            }
+            WebhookPayload::Dismiss => {
+                info!("Received dismiss action. Closing notification dialog.");
+                let app_handle_clone = app_handle.clone();
+                if let Err(e) = app_handle.run_on_main_thread(move || {
+                    if let Some(existing_window) =
+                        app_handle_clone.get_webview_window("notification_dialog")
+                    {
+                        if let Err(e) = existing_window.close() {
+                            error!("Failed to close notification dialog: {}", e);
+                        }
+                    }
+                }) {
+                    error!("Failed to run on main thread: {}", e);
+                }
+            }
+            WebhookPayload::UpdateText { text } => {
+                info!("Received update-text action.");
+                let app_handle_clone = app_handle.clone();
+                if let Err(e) = app_handle.run_on_main_thread(move || {
+                    if let Some(existing_window) =
+                        app_handle_clone.get_webview_window("notification_dialog")
+                    {
+                        if let Err(e) =
+                            existing_window.emit("update_notification_text", &text)
+                        {
+                            error!("Failed to emit update-text event: {}", e);
+                        }
+                    } else {
+                        error!("No notification dialog to update");
+                    }
+                }) {
+                    error!("Failed to run on main thread: {}", e);
                 }
-            } else {
-                error!("Failed to parse webhook payload");
-                let response = Response::from_string("Invalid payload").with_status_code(400);
-                if let Err(e) = request.respond(response) {
-                    error!("Failed to respond with error: {}", e);
+            }
+            WebhookPayload::RunCommand { path, args } => {
+                let settings = Settings::load(&state.settings);
+                if settings.allow_run_command {
+                    info!("Received run-command action for '{}'.", path);
+                    thread::spawn(move || {
+                        let runner = RealCommandRunner;
+                        let arg_refs: Vec<&str> =
+                            args.iter().map(String::as_str).collect();
+                        match runner.run(&path, &arg_refs) {
+                            Ok(output) if !output.success => error!(
+                                "RunCommand '{}' exited with status {:?}: {}",
+                                path,
+                                output.status_code,
+                                String::from_utf8_lossy(&output.stderr)
+                            ),
+                            Ok(_) => info!("RunCommand '{}' executed successfully", path),
+                            Err(e) => {
+                                error!("Failed to execute RunCommand '{}': {}", path, e)
+                            }
+                        }
+                    });
+                } else {
+                    error!(
+                        "Rejected RunCommand action: allow_run_command is disabled in settings"
+                    );
                 }
             }
         }
-    });
+
+        finish(request, text_response(200, "Action processed"));
+    } else {
+        error!("Failed to parse webhook payload");
+        finish(request, text_response(400, "Invalid payload"));
+    }
 }
 
 #[cfg(test)]
@@ -254,34 +762,132 @@ mod tests {
     use super::*;
     use tiny_http::Header;
 
+    const SECRET: &str = "my_secret_token";
+    const BODY: &str = r#"{"text":"hi","duration_ms":3000}"#;
+    const PORT: u16 = 54321;
+
+    fn signed_headers(secret: &str, timestamp: i64, body: &str) -> Vec<Header> {
+        let timestamp_str = timestamp.to_string();
+        let signature = compute_signature(secret, &timestamp_str, body);
+        vec![
+            Header::from_bytes("Content-Type", "application/json").unwrap(),
+            Header::from_bytes("Host", format!("127.0.0.1:{}", PORT)).unwrap(),
+            Header::from_bytes("X-Daylit-Timestamp", timestamp_str).unwrap(),
+            Header::from_bytes("X-Daylit-Signature", signature).unwrap(),
+        ]
+    }
+
     #[test]
     fn test_validate_request_success() {
-        let secret = "my_secret_token";
-        let headers = vec![
-            Header::from_bytes("Content-Type", "application/json").unwrap(),
-            Header::from_bytes("X-Daylit-Secret", "my_secret_token").unwrap(),
-        ];
-        assert!(validate_request(&headers, secret));
+        let headers = signed_headers(SECRET, current_unix_time(), BODY);
+        let seen = Mutex::new(HashSet::new());
+        assert!(validate_request(&headers, BODY, SECRET, PORT, &seen));
     }
 
     #[test]
     fn test_validate_request_failure_wrong_secret() {
-        let secret = "my_secret_token";
-        let headers = vec![Header::from_bytes("X-Daylit-Secret", "wrong_token").unwrap()];
-        assert!(!validate_request(&headers, secret));
+        let headers = signed_headers("a_different_secret", current_unix_time(), BODY);
+        let seen = Mutex::new(HashSet::new());
+        assert!(!validate_request(&headers, BODY, SECRET, PORT, &seen));
     }
 
     #[test]
-    fn test_validate_request_failure_missing_header() {
-        let secret = "my_secret_token";
+    fn test_validate_request_failure_missing_headers() {
         let headers = vec![Header::from_bytes("Content-Type", "application/json").unwrap()];
-        assert!(!validate_request(&headers, secret));
+        let seen = Mutex::new(HashSet::new());
+        assert!(!validate_request(&headers, BODY, SECRET, PORT, &seen));
+    }
+
+    #[test]
+    fn test_validate_request_failure_stale_timestamp() {
+        let headers = signed_headers(SECRET, current_unix_time() - REQUEST_WINDOW_SECS - 1, BODY);
+        let seen = Mutex::new(HashSet::new());
+        assert!(!validate_request(&headers, BODY, SECRET, PORT, &seen));
+    }
+
+    #[test]
+    fn test_validate_request_failure_tampered_body() {
+        let headers = signed_headers(SECRET, current_unix_time(), BODY);
+        let seen = Mutex::new(HashSet::new());
+        assert!(!validate_request(&headers, r#"{"text":"tampered"}"#, SECRET, PORT, &seen));
     }
 
     #[test]
-    fn test_validate_request_case_insensitive_header_name() {
-        let secret = "my_secret_token";
-        let headers = vec![Header::from_bytes("x-daylit-secret", "my_secret_token").unwrap()];
-        assert!(validate_request(&headers, secret));
+    fn test_validate_request_rejects_replay() {
+        let headers = signed_headers(SECRET, current_unix_time(), BODY);
+        let seen = Mutex::new(HashSet::new());
+        assert!(validate_request(&headers, BODY, SECRET, PORT, &seen));
+        // The exact same (timestamp, signature) pair presented again must
+        // be rejected even though it was valid the first time.
+        assert!(!validate_request(&headers, BODY, SECRET, PORT, &seen));
+    }
+
+    #[test]
+    fn test_validate_request_rejects_spoofed_host() {
+        let timestamp = current_unix_time();
+        let mut headers = signed_headers(SECRET, timestamp, BODY);
+        headers.retain(|h| !h.field.as_str().as_str().eq_ignore_ascii_case("Host"));
+        headers.push(Header::from_bytes("Host", "evil.example.com:54321").unwrap());
+        let seen = Mutex::new(HashSet::new());
+        assert!(!validate_request(&headers, BODY, SECRET, PORT, &seen));
+    }
+
+    #[test]
+    fn test_validate_request_rejects_origin() {
+        let mut headers = signed_headers(SECRET, current_unix_time(), BODY);
+        headers.push(Header::from_bytes("Origin", "http://evil.example.com").unwrap());
+        let seen = Mutex::new(HashSet::new());
+        assert!(!validate_request(&headers, BODY, SECRET, PORT, &seen));
+    }
+
+    #[test]
+    fn test_hardened_response_sets_security_headers() {
+        let response = text_response(200, "hi");
+        let get = |name: &str| header_value(response.headers(), name).map(str::to_string);
+
+        assert_eq!(get("Content-Type").as_deref(), Some("text/plain; charset=utf-8"));
+        assert_eq!(get("Cache-Control").as_deref(), Some("no-store"));
+        assert_eq!(get("X-Content-Type-Options").as_deref(), Some("nosniff"));
+        assert_eq!(get("X-Frame-Options").as_deref(), Some("DENY"));
+        assert_eq!(get("Referrer-Policy").as_deref(), Some("no-referrer"));
+    }
+
+    #[test]
+    fn test_json_response_serializes_body() {
+        let response = json_response(200, &QueueStatus { pending: 2, last_delivered_at: Some(42) });
+        assert_eq!(
+            header_value(response.headers(), "Content-Type"),
+            Some("application/json")
+        );
+    }
+
+    #[test]
+    fn test_parse_lockfile_pid() {
+        assert_eq!(parse_lockfile_pid("127.0.0.1:54321|1234|sometoken"), Some(1234));
+        assert_eq!(parse_lockfile_pid("malformed"), None);
+    }
+
+    #[test]
+    fn test_remove_stale_lockfile_removes_when_pid_is_dead() {
+        let path = std::env::temp_dir().join(format!("daylit-tray-test-{}.lock", std::process::id()));
+        // PID 0 is never a real user process we own, and isn't present in
+        // `sysinfo`'s process table, so this simulates a dead owner.
+        fs::write(&path, "127.0.0.1:54321|0|sometoken").unwrap();
+
+        remove_stale_lockfile(&path);
+
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_remove_stale_lockfile_keeps_live_pid() {
+        let path = std::env::temp_dir().join(format!("daylit-tray-test-live-{}.lock", std::process::id()));
+        let our_pid = std::process::id();
+        fs::write(&path, format!("127.0.0.1:54321|{}|sometoken", our_pid)).unwrap();
+
+        remove_stale_lockfile(&path);
+
+        assert!(path.exists());
+        fs::remove_file(&path).unwrap();
     }
 }
@@ -1,10 +1,19 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
 use tauri::Wry;
 use tauri_plugin_store::Store;
 
 pub const LOCKFILE_NAME: &str = "daylit-tray.lock";
 
+pub(crate) fn current_unix_time() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system clock is before the epoch")
+        .as_secs() as i64
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(default)]
 pub struct Settings {
@@ -13,6 +22,17 @@ pub struct Settings {
     pub lockfile_dir: Option<String>,
     pub daylit_path: Option<String>,
     pub use_native_notifications: bool,
+    // Gates the `RunCommand` webhook action, since it executes an
+    // arbitrary local binary; off unless the user opts in.
+    pub allow_run_command: bool,
+    // `host:port` to bind the webhook server to; `None` keeps the default
+    // loopback-only `127.0.0.1:0`. Binding to anything else requires
+    // `enable_tls`, since the webhook would otherwise cross the network
+    // unencrypted (each request body along with its HMAC signature).
+    pub bind_address: Option<String>,
+    pub enable_tls: bool,
+    pub tls_cert_path: Option<String>,
+    pub tls_key_path: Option<String>,
 }
 
 impl Default for Settings {
@@ -23,6 +43,11 @@ impl Default for Settings {
             lockfile_dir: None,
             daylit_path: None,
             use_native_notifications: false,
+            allow_run_command: false,
+            bind_address: None,
+            enable_tls: false,
+            tls_cert_path: None,
+            tls_key_path: None,
         }
     }
 }
@@ -36,10 +61,82 @@ impl Settings {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug, Default)]
-pub struct WebhookPayload {
-    pub text: String,
-    pub duration_ms: u32,
+/// A single webhook call dispatches one of these actions. `Notify` is the
+/// default for backward compatibility: a legacy `{ text, duration_ms }`
+/// body with no `action` tag is parsed as `Notify` by [`parse_webhook_payload`].
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(tag = "action")]
+pub enum WebhookPayload {
+    Notify {
+        text: String,
+        duration_ms: u32,
+        // Higher values jump ahead of already-queued lower-priority
+        // notifications; omitted (or equal priority) preserves arrival order.
+        #[serde(default)]
+        priority: Option<u8>,
+    },
+    Dismiss,
+    UpdateText { text: String },
+    RunCommand { path: String, args: Vec<String> },
+}
+
+/// Parse a webhook body into a [`WebhookPayload`]. Bodies with no `action`
+/// field are the pre-dispatch `{ text, duration_ms }` shape and default to
+/// `Notify`, so existing integrations keep working unmodified.
+pub fn parse_webhook_payload(body: &str) -> serde_json::Result<WebhookPayload> {
+    let mut value: serde_json::Value = serde_json::from_str(body)?;
+    if let serde_json::Value::Object(map) = &mut value {
+        map.entry("action")
+            .or_insert_with(|| serde_json::Value::String("Notify".to_string()));
+    }
+    serde_json::from_value(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_webhook_payload_legacy_body_defaults_to_notify() {
+        let payload = parse_webhook_payload(r#"{"text":"hi","duration_ms":3000}"#).unwrap();
+        match payload {
+            WebhookPayload::Notify {
+                text,
+                duration_ms,
+                priority,
+            } => {
+                assert_eq!(text, "hi");
+                assert_eq!(duration_ms, 3000);
+                assert_eq!(priority, None);
+            }
+            _ => panic!("expected Notify"),
+        }
+    }
+
+    #[test]
+    fn test_parse_webhook_payload_explicit_action() {
+        let payload = parse_webhook_payload(r#"{"action":"Dismiss"}"#).unwrap();
+        assert!(matches!(payload, WebhookPayload::Dismiss));
+    }
+
+    #[test]
+    fn test_parse_webhook_payload_run_command() {
+        let payload =
+            parse_webhook_payload(r#"{"action":"RunCommand","path":"daylit","args":["notify"]}"#)
+                .unwrap();
+        match payload {
+            WebhookPayload::RunCommand { path, args } => {
+                assert_eq!(path, "daylit");
+                assert_eq!(args, vec!["notify".to_string()]);
+            }
+            _ => panic!("expected RunCommand"),
+        }
+    }
+
+    #[test]
+    fn test_parse_webhook_payload_invalid_body() {
+        assert!(parse_webhook_payload("not json").is_err());
+    }
 }
 
 // Event payload for when we re-use an existing window
@@ -49,10 +146,297 @@ pub struct UpdatePayload {
     pub duration_ms: u32,
 }
 
-// Main application state, holds settings store and last payload
+pub const NOTIFICATION_HISTORY_CAPACITY: usize = 100;
+
+/// The subset of a `WebhookPayload` that gets queued and displayed as a
+/// dialog. `Dismiss`/`UpdateText`/`RunCommand` act on whatever is on screen
+/// right now, so they never take a turn in this queue.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct QueuedNotification {
+    pub text: String,
+    pub duration_ms: u32,
+    #[serde(default)]
+    pub priority: Option<u8>,
+}
+
+/// A notification that has already been shown, stamped with the unix time
+/// it was delivered, so the frontend can render a history panel.
+#[derive(Clone, Serialize, Debug)]
+pub struct NotificationHistoryEntry {
+    pub notification: QueuedNotification,
+    pub delivered_at: i64,
+}
+
+/// FIFO queue of notifications awaiting display, with priority preemption,
+/// plus a capped ring-buffer of ones already shown, so a burst of webhooks
+/// queues up instead of clobbering whatever dialog is currently on screen.
+#[derive(Default)]
+pub struct NotificationQueue {
+    pending: Mutex<VecDeque<QueuedNotification>>,
+    history: Mutex<VecDeque<NotificationHistoryEntry>>,
+}
+
+impl NotificationQueue {
+    /// Items with a higher `priority` jump ahead of already-queued
+    /// lower-priority items; items of equal (or unset, which counts as 0)
+    /// priority keep arrival order.
+    pub fn enqueue(&self, notification: QueuedNotification) {
+        let mut pending = self.pending.lock().expect("Failed to acquire queue lock");
+        let new_priority = notification.priority.unwrap_or(0);
+        let insert_at = pending
+            .iter()
+            .position(|queued| queued.priority.unwrap_or(0) < new_priority)
+            .unwrap_or(pending.len());
+        pending.insert(insert_at, notification);
+    }
+
+    /// Pop the next pending notification and record it in history, stamped
+    /// with `delivered_at`.
+    pub fn advance(&self, delivered_at: i64) -> Option<QueuedNotification> {
+        let notification = self
+            .pending
+            .lock()
+            .expect("Failed to acquire queue lock")
+            .pop_front()?;
+
+        let mut history = self
+            .history
+            .lock()
+            .expect("Failed to acquire history lock");
+        history.push_back(NotificationHistoryEntry {
+            notification: notification.clone(),
+            delivered_at,
+        });
+        while history.len() > NOTIFICATION_HISTORY_CAPACITY {
+            history.pop_front();
+        }
+
+        Some(notification)
+    }
+
+    /// Snapshot the pending queue without mutating it, for list rendering.
+    pub fn peek(&self) -> Vec<QueuedNotification> {
+        self.pending
+            .lock()
+            .expect("Failed to acquire queue lock")
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    pub fn history(&self) -> Vec<NotificationHistoryEntry> {
+        self.history
+            .lock()
+            .expect("Failed to acquire history lock")
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    pub fn clear_history(&self) {
+        self.history
+            .lock()
+            .expect("Failed to acquire history lock")
+            .clear();
+    }
+
+    /// The timestamp of the most recently delivered notification, for the
+    /// `/status` endpoint.
+    pub fn last_delivered_at(&self) -> Option<i64> {
+        self.history
+            .lock()
+            .expect("Failed to acquire history lock")
+            .back()
+            .map(|entry| entry.delivered_at)
+    }
+}
+
+// Main application state, holds settings store and the notification queue
 pub struct AppState {
     pub settings: Arc<Store<Wry>>,
-    pub payload: Mutex<Option<WebhookPayload>>,
+    pub queue: NotificationQueue,
+    pub webhook_server: crate::server::WebhookServer,
     pub lockfile_path: Mutex<Option<std::path::PathBuf>>,
+    pub port: Mutex<Option<u16>>,
+    // The full `host:port` the webhook server is actually bound to, as
+    // published in the lockfile; differs from `port` once LAN binding is in
+    // use (`port` alone doesn't tell a companion CLI which interface to hit).
+    pub bound_address: Mutex<Option<String>>,
     pub secret: Mutex<Option<String>>,
+    // `(timestamp, signature)` pairs accepted within the replay window,
+    // pruned lazily so a captured request can't be replayed verbatim.
+    pub seen_signatures: Mutex<HashSet<(i64, String)>>,
+    // Tracks which window labels care about which event class, so future
+    // event producers can fan out to a dynamic set of windows (e.g. several
+    // notification dialogs) via `emit_filter` instead of a fixed label list.
+    pub event_subscribers: EventSubscribers,
+}
+
+#[derive(Default)]
+pub struct EventSubscribers(Mutex<HashMap<String, HashSet<String>>>);
+
+impl EventSubscribers {
+    pub fn subscribe(&self, event_class: &str, window_label: &str) {
+        self.0
+            .lock()
+            .expect("Failed to acquire event_subscribers lock")
+            .entry(event_class.to_string())
+            .or_default()
+            .insert(window_label.to_string());
+    }
+
+    pub fn unsubscribe(&self, event_class: &str, window_label: &str) {
+        if let Some(labels) = self
+            .0
+            .lock()
+            .expect("Failed to acquire event_subscribers lock")
+            .get_mut(event_class)
+        {
+            labels.remove(window_label);
+        }
+    }
+
+    pub fn subscribers(&self, event_class: &str) -> HashSet<String> {
+        self.0
+            .lock()
+            .expect("Failed to acquire event_subscribers lock")
+            .get(event_class)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod event_subscriber_tests {
+    use super::*;
+
+    #[test]
+    fn test_subscribe_and_unsubscribe() {
+        let subscribers = EventSubscribers::default();
+        subscribers.subscribe("settings-updated", "notification_dialog_2");
+        assert!(subscribers
+            .subscribers("settings-updated")
+            .contains("notification_dialog_2"));
+
+        subscribers.unsubscribe("settings-updated", "notification_dialog_2");
+        assert!(!subscribers
+            .subscribers("settings-updated")
+            .contains("notification_dialog_2"));
+    }
+
+    #[test]
+    fn test_subscribers_empty_by_default() {
+        let subscribers = EventSubscribers::default();
+        assert!(subscribers.subscribers("settings-updated").is_empty());
+    }
+}
+
+#[cfg(test)]
+mod queue_tests {
+    use super::*;
+
+    fn notification(text: &str) -> QueuedNotification {
+        QueuedNotification {
+            text: text.to_string(),
+            duration_ms: 3000,
+            priority: None,
+        }
+    }
+
+    fn prioritized(text: &str, priority: u8) -> QueuedNotification {
+        QueuedNotification {
+            text: text.to_string(),
+            duration_ms: 3000,
+            priority: Some(priority),
+        }
+    }
+
+    #[test]
+    fn test_queue_advances_in_fifo_order() {
+        let queue = NotificationQueue::default();
+        queue.enqueue(notification("first"));
+        queue.enqueue(notification("second"));
+
+        assert_eq!(queue.advance(0).unwrap().text, "first");
+        assert_eq!(queue.advance(0).unwrap().text, "second");
+        assert!(queue.advance(0).is_none());
+    }
+
+    #[test]
+    fn test_queue_priority_preemption() {
+        let queue = NotificationQueue::default();
+        queue.enqueue(notification("normal-1"));
+        queue.enqueue(prioritized("urgent", 5));
+        queue.enqueue(notification("normal-2"));
+        // Equal priority keeps arrival order relative to the other
+        // priority-5 item rather than jumping ahead of it.
+        queue.enqueue(prioritized("also-urgent", 5));
+
+        assert_eq!(queue.advance(0).unwrap().text, "urgent");
+        assert_eq!(queue.advance(0).unwrap().text, "also-urgent");
+        assert_eq!(queue.advance(0).unwrap().text, "normal-1");
+        assert_eq!(queue.advance(0).unwrap().text, "normal-2");
+        assert!(queue.advance(0).is_none());
+    }
+
+    #[test]
+    fn test_peek_does_not_consume() {
+        let queue = NotificationQueue::default();
+        queue.enqueue(notification("first"));
+
+        assert_eq!(queue.peek().len(), 1);
+        assert_eq!(queue.peek().len(), 1);
+    }
+
+    #[test]
+    fn test_advance_records_history_with_timestamp() {
+        let queue = NotificationQueue::default();
+        queue.enqueue(notification("first"));
+        queue.advance(1234);
+
+        let history = queue.history();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].notification.text, "first");
+        assert_eq!(history[0].delivered_at, 1234);
+    }
+
+    #[test]
+    fn test_history_is_capped() {
+        let queue = NotificationQueue::default();
+        for i in 0..NOTIFICATION_HISTORY_CAPACITY + 10 {
+            queue.enqueue(notification(&i.to_string()));
+            queue.advance(i as i64);
+        }
+
+        let history = queue.history();
+        assert_eq!(history.len(), NOTIFICATION_HISTORY_CAPACITY);
+        // The oldest entries should have been evicted, so the earliest
+        // surviving delivery is the 11th one (index 10).
+        assert_eq!(history[0].delivered_at, 10);
+    }
+
+    #[test]
+    fn test_last_delivered_at() {
+        let queue = NotificationQueue::default();
+        assert_eq!(queue.last_delivered_at(), None);
+
+        queue.enqueue(notification("first"));
+        queue.advance(100);
+        assert_eq!(queue.last_delivered_at(), Some(100));
+
+        queue.enqueue(notification("second"));
+        queue.advance(200);
+        assert_eq!(queue.last_delivered_at(), Some(200));
+    }
+
+    #[test]
+    fn test_clear_history() {
+        let queue = NotificationQueue::default();
+        queue.enqueue(notification("first"));
+        queue.advance(0);
+        assert_eq!(queue.history().len(), 1);
+
+        queue.clear_history();
+        assert!(queue.history().is_empty());
+    }
 }